@@ -0,0 +1,167 @@
+use std::fmt::Display;
+
+use indoc::formatdoc;
+
+use crate::shell::{ActivateOptions, Shell};
+
+/// Command Prompt (`cmd.exe`).
+///
+/// `cmd.exe` has no equivalent of a `chpwd`/`LocationChangedAction` event, so
+/// there is no reliable way to notice a `cd` on its own. When
+/// [Clink](https://chrisant996.github.io/clink/clink.html) is loaded we hook
+/// `mise hook-env` in through its Lua prompt filter API, which re-runs on
+/// every prompt redraw (i.e. after every command, including `cd`); without
+/// Clink we fall back to just re-running the hook once at activation time.
+#[derive(Default)]
+pub struct Cmd {}
+
+impl Shell for Cmd {
+    fn activate(&self, opts: ActivateOptions) -> String {
+        let exe = opts.exe;
+        let flags = opts.flags;
+        let exe = exe.to_string_lossy();
+        let mut out = String::new();
+
+        out.push_str(&formatdoc! {r#"
+            @echo off
+            setlocal enabledelayedexpansion
+            set "MISE_SHELL=cmd"
+            set "__MISE_ORIG_PATH=%PATH%"
+
+            doskey mise="{exe}" $*
+            "#});
+
+        if !opts.no_hook_env {
+            out.push_str(&formatdoc! {r#"
+
+            if defined CLINK_VERSION (
+                set "__MISE_CLINK_DIR=%TEMP%\_mise_clink"
+                if not exist "%__MISE_CLINK_DIR%" mkdir "%__MISE_CLINK_DIR%" >nul 2>&1
+                (
+                    echo local function mise_prompt_filter^(^)
+                    echo     local f = io.popen^('"{exe}" hook-env{flags} -s cmd'^)
+                    echo     local out = f:read^('*a'^)
+                    echo     f:close^(^)
+                    echo     if out and #out ^> 0 then os.execute^(out^) end
+                    echo end
+                    echo clink.promptfilter^(1^):filter = mise_prompt_filter
+                ) > "%__MISE_CLINK_DIR%\mise_hook.lua"
+                clink installscripts "%__MISE_CLINK_DIR%" >nul 2>&1
+            )
+
+            for /f "delims=" %%i in ('"{exe}" hook-env{flags} -s cmd') do %%i
+            "#});
+        }
+        out
+    }
+
+    fn deactivate(&self) -> String {
+        formatdoc! {r#"
+        doskey mise=
+        set "MISE_SHELL="
+        set "__MISE_WATCH="
+        set "__MISE_DIFF="
+        "#}
+    }
+
+    fn set_env(&self, k: &str, v: &str) -> String {
+        let v = cmd_escape(v);
+        format!("set \"{k}={v}\"\n")
+    }
+
+    fn prepend_env(&self, k: &str, v: &str) -> String {
+        let v = normalize_path(v);
+        let v = cmd_escape(&v);
+        format!("set \"{k}={v};%{k}%\"\n")
+    }
+
+    fn unset_env(&self, k: &str) -> String {
+        format!("set \"{k}=\"\n")
+    }
+}
+
+impl Display for Cmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cmd")
+    }
+}
+
+/// Strip the `\\?\` verbatim prefix Windows sometimes puts on canonicalized
+/// paths (e.g. from `std::fs::canonicalize`) before it is written into a cmd
+/// environment variable, since `cmd.exe`/most tools it shells out to don't
+/// understand verbatim paths.
+fn normalize_path(v: &str) -> String {
+    dunce::simplified(std::path::Path::new(v))
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Escape a value for use unquoted inside a `cmd.exe` `set "K=V"` assignment.
+/// `%` must be doubled or it is treated as a variable reference. `!` only
+/// needs a caret escape when delayed expansion is enabled, since that's the
+/// pass that would otherwise consume a bare `!`; `activate()` turns on
+/// `setlocal enabledelayedexpansion` for exactly this reason, so the escape
+/// below holds regardless of what the surrounding session had it set to
+/// before activation.
+fn cmd_escape(s: &str) -> String {
+    s.replace('%', "%%").replace('!', "^!")
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use std::path::Path;
+    use test_log::test;
+
+    use crate::test::replace_path;
+
+    use super::*;
+
+    #[test]
+    fn test_activate() {
+        let cmd = Cmd::default();
+        let exe = Path::new("/some/dir/mise");
+        let opts = ActivateOptions {
+            exe: exe.to_path_buf(),
+            flags: " --status".into(),
+            no_hook_env: false,
+        };
+        assert_snapshot!(cmd.activate(opts));
+    }
+
+    #[test]
+    fn test_set_env() {
+        assert_snapshot!(Cmd::default().set_env("FOO", "1"));
+    }
+
+    #[test]
+    fn test_set_env_escapes_percent_and_bang() {
+        assert_snapshot!(Cmd::default().set_env("FOO", "100%!done"));
+    }
+
+    #[test]
+    fn test_prepend_env() {
+        let cmd = Cmd::default();
+        assert_snapshot!(replace_path(&cmd.prepend_env("PATH", "/some/dir:/2/dir")));
+    }
+
+    // `dunce::simplified` is a documented no-op on non-Windows targets, so this only
+    // exercises the verbatim-prefix strip (and only produces a stable snapshot) on Windows.
+    #[test]
+    #[cfg(windows)]
+    fn test_prepend_env_strips_verbatim_prefix() {
+        let cmd = Cmd::default();
+        assert_snapshot!(cmd.prepend_env("PATH", r"\\?\C:\tools\bin"));
+    }
+
+    #[test]
+    fn test_unset_env() {
+        assert_snapshot!(Cmd::default().unset_env("FOO"));
+    }
+
+    #[test]
+    fn test_deactivate() {
+        let deactivate = Cmd::default().deactivate();
+        assert_snapshot!(replace_path(&deactivate));
+    }
+}
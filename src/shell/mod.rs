@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+mod cmd;
+mod powershell;
+mod pwsh;
+mod pwsh_common;
+
+pub use cmd::Cmd;
+pub use powershell::PowerShell;
+pub use pwsh::Pwsh;
+
+/// Options passed to [`Shell::activate`] describing how the emitted init
+/// script should invoke `mise` and whether it should wire up the
+/// `hook-env` re-evaluation machinery.
+pub struct ActivateOptions {
+    pub exe: PathBuf,
+    pub flags: String,
+    pub no_hook_env: bool,
+}
+
+/// A shell mise can emit activation scripts and env mutations for.
+pub trait Shell {
+    fn activate(&self, opts: ActivateOptions) -> String;
+    fn deactivate(&self) -> String;
+    fn set_env(&self, k: &str, v: &str) -> String;
+    fn prepend_env(&self, k: &str, v: &str) -> String;
+    fn unset_env(&self, k: &str) -> String;
+}
+
+/// Resolve a shell name, as passed to `mise activate <name>`, to its
+/// [`Shell`] implementation.
+pub fn get_shell(shell: &str) -> Option<Box<dyn Shell>> {
+    match shell {
+        "pwsh" => Some(Box::new(Pwsh::default())),
+        "powershell" => Some(Box::new(PowerShell::default())),
+        "cmd" => Some(Box::new(Cmd::default())),
+        _ => None,
+    }
+}
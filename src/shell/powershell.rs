@@ -0,0 +1,134 @@
+use std::fmt::Display;
+
+use crate::shell::pwsh_common::{
+    deactivate_script, hook_function, mise_function, prepend_env_script, prompt_wrap_hook,
+    set_env_script, unset_env_script,
+};
+use crate::shell::{ActivateOptions, Shell};
+
+/// Windows PowerShell 5.1 (`powershell.exe`), as distinct from PowerShell
+/// Core (see [`Pwsh`](crate::shell::pwsh::Pwsh)). 5.1 doesn't have
+/// `$ExecutionContext.SessionState.InvokeCommand.LocationChangedAction`
+/// (added in 6.2), so on a `cd` it would otherwise never notice the
+/// directory changed until something else redrew the prompt. Instead of
+/// wiring `LocationChangedAction`, this backend hooks directory changes
+/// purely by wrapping `prompt`, chaining whatever `prompt` function the
+/// user already had defined. Everything else (the `mise` wrapper function,
+/// `deactivate`, and env-mutation lines) is shared with `Pwsh` via
+/// `pwsh_common`.
+#[derive(Default)]
+pub struct PowerShell {}
+
+impl Shell for PowerShell {
+    fn activate(&self, opts: ActivateOptions) -> String {
+        let exe = opts.exe.to_string_lossy().to_string();
+        let flags = opts.flags;
+
+        let mut out = format!(
+            "$env:MISE_SHELL = 'powershell'\n$env:__MISE_ORIG_PATH = $env:PATH\n\n{}",
+            mise_function(&exe, "powershell")
+        );
+
+        if !opts.no_hook_env {
+            out.push('\n');
+            out.push_str(&hook_function(&exe, &flags, "powershell"));
+            out.push('\n');
+            out.push_str(&prompt_wrap_hook("powershell"));
+            out.push_str("\n_mise_hook\n");
+        }
+        out
+    }
+
+    fn deactivate(&self) -> String {
+        deactivate_script()
+    }
+
+    fn set_env(&self, k: &str, v: &str) -> String {
+        set_env_script(k, v)
+    }
+
+    fn prepend_env(&self, k: &str, v: &str) -> String {
+        prepend_env_script(k, v)
+    }
+
+    fn unset_env(&self, k: &str) -> String {
+        unset_env_script(k)
+    }
+}
+
+impl Display for PowerShell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "powershell")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use std::path::Path;
+    use test_log::test;
+
+    use crate::test::replace_path;
+
+    use super::*;
+
+    #[test]
+    fn test_activate() {
+        let powershell = PowerShell::default();
+        let exe = Path::new("/some/dir/mise");
+        let opts = ActivateOptions {
+            exe: exe.to_path_buf(),
+            flags: " --status".into(),
+            no_hook_env: false,
+        };
+        assert_snapshot!(powershell.activate(opts));
+    }
+
+    #[test]
+    fn test_set_env() {
+        assert_snapshot!(PowerShell::default().set_env("FOO", "1"));
+    }
+
+    #[test]
+    fn test_set_env_with_space() {
+        assert_snapshot!(PowerShell::default().set_env("FOO", "hello world"));
+    }
+
+    #[test]
+    fn test_set_env_with_single_quote() {
+        assert_snapshot!(PowerShell::default().set_env("FOO", "it's a test"));
+    }
+
+    #[test]
+    fn test_set_env_with_dollar_sign() {
+        assert_snapshot!(PowerShell::default().set_env("FOO", "$HOME/bin"));
+    }
+
+    #[test]
+    fn test_set_env_with_newline() {
+        assert_snapshot!(PowerShell::default().set_env("FOO", "line one\nline two"));
+    }
+
+    #[test]
+    fn test_prepend_env() {
+        let powershell = PowerShell::default();
+        assert_snapshot!(replace_path(&powershell.prepend_env("PATH", "/some/dir:/2/dir")));
+    }
+
+    #[test]
+    fn test_prepend_env_with_newline() {
+        let powershell = PowerShell::default();
+        assert_snapshot!(powershell.prepend_env("PATH", "line one\nline two"));
+    }
+
+    #[test]
+    fn test_unset_env() {
+        assert_snapshot!(PowerShell::default().unset_env("FOO"));
+    }
+
+    #[test]
+    fn test_deactivate() {
+        let deactivate = PowerShell::default().deactivate();
+        assert_snapshot!(replace_path(&deactivate));
+    }
+}
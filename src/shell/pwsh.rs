@@ -1,8 +1,9 @@
-use std::borrow::Cow;
 use std::fmt::Display;
 
-use indoc::formatdoc;
-
+use crate::shell::pwsh_common::{
+    chpwd_hook, deactivate_script, hook_function, mise_function, prepend_env_script,
+    prompt_wrap_hook, set_env_script, unset_env_script,
+};
 use crate::shell::{ActivateOptions, Shell};
 
 #[derive(Default)]
@@ -10,120 +11,40 @@ pub struct Pwsh {}
 
 impl Shell for Pwsh {
     fn activate(&self, opts: ActivateOptions) -> String {
-        let exe = opts.exe;
+        let exe = opts.exe.to_string_lossy().to_string();
         let flags = opts.flags;
-        let exe = exe.to_string_lossy();
-        let mut out = String::new();
-
-        out.push_str(&formatdoc! {r#"
-            $env:MISE_SHELL = 'pwsh'
-            $env:__MISE_ORIG_PATH = $env:PATH
-
-            function mise {{
-
-                $code = [System.Management.Automation.Language.Parser]::ParseInput($MyInvocation.Statement.Substring($MyInvocation.OffsetInLine - 1), [ref]$null, [ref]$null)
-                $myLine = $code.Find({{ $args[0].CommandElements }}, $true).CommandElements | ForEach-Object {{ $_.ToString() }} | Join-String -Separator ' '
-                $command, [array]$arguments = Invoke-Expression ('Write-Output -- ' + $myLine)
-                
-                if ($null -eq $arguments) {{ 
-                    & {exe}
-                    return
-                }} 
-
-                $command = $arguments[0]
-                $arguments = $arguments[1..$arguments.Length]
-
-                if ($arguments -contains '--help') {{
-                    return & {exe} $command $arguments 
-                }}
-
-                switch ($command) {{
-                    {{ $_ -in 'deactivate', 'shell', 'sh' }} {{
-                        if ($arguments -contains '-h' -or $arguments -contains '--help') {{
-                            & {exe} $command $arguments
-                        }}
-                        else {{
-                            & {exe} $command $arguments | Out-String | Invoke-Expression -ErrorAction SilentlyContinue
-                        }}
-                    }}
-                    default {{
-                        & {exe} $command $arguments
-                        $status = $LASTEXITCODE
-                        if ($(Test-Path -Path Function:\_mise_hook)){{
-                            _mise_hook
-                        }}
-                        pwsh -NoProfile -Command exit $status #Pass down exit code from mise after _mise_hook
-                    }}
-                }}
-            }}
-            "#});
+
+        let mut out = format!(
+            "$env:MISE_SHELL = 'pwsh'\n$env:__MISE_ORIG_PATH = $env:PATH\n\n{}",
+            mise_function(&exe, "pwsh")
+        );
 
         if !opts.no_hook_env {
-            out.push_str(&formatdoc! {r#"
-
-            function _mise_hook {{
-                if ($env:MISE_SHELL -eq "pwsh"){{
-                    & {exe} hook-env{flags} -s pwsh | Out-String | Invoke-Expression -ErrorAction SilentlyContinue
-                }}
-            }}
-
-            if (-not $__mise_pwsh_previous_chpwd_function){{
-                $_mise_chpwd_hook = [EventHandler[System.Management.Automation.LocationChangedEventArgs]] {{
-                    param([object] $source, [System.Management.Automation.LocationChangedEventArgs] $eventArgs)
-                    end {{
-                        _mise_hook
-                    }}
-                }};
-                $Global:__mise_pwsh_previous_chpwd_function=$ExecutionContext.SessionState.InvokeCommand.LocationChangedAction;
-
-                if ($__mise_original_pwsh_chpwd_function) {{
-                    $ExecutionContext.SessionState.InvokeCommand.LocationChangedAction = [Delegate]::Combine($__mise_pwsh_previous_chpwd_function, $_mise_chpwd_hook)
-                }}
-                else {{
-                    $ExecutionContext.SessionState.InvokeCommand.LocationChangedAction = $_mise_chpwd_hook
-                }}
-            }}
-
-            if (-not $__mise_pwsh_previous_prompt_function){{
-                $global:__mise_pwsh_previous_prompt_function=$function:prompt
-                function global:prompt {{
-                    if (Test-Path -Path Function:\_mise_hook){{
-                        _mise_hook
-                    }}
-                    & $__mise_pwsh_previous_prompt_function
-                }}
-            }}
-
-            _mise_hook
-            "#});
+            out.push('\n');
+            out.push_str(&hook_function(&exe, &flags, "pwsh"));
+            out.push('\n');
+            out.push_str(&chpwd_hook());
+            out.push('\n');
+            out.push_str(&prompt_wrap_hook("pwsh"));
+            out.push_str("\n_mise_hook\n");
         }
         out
     }
 
     fn deactivate(&self) -> String {
-        formatdoc! {r#"
-        Remove-Item function:mise
-        Remove-Item -Path Env:/MISE_SHELL
-        Remove-Item -Path Env:/__MISE_WATCH
-        Remove-Item -Path Env:/__MISE_DIFF
-        "#}
+        deactivate_script()
     }
 
     fn set_env(&self, k: &str, v: &str) -> String {
-        let k = powershell_escape(k.into());
-        let v = powershell_escape(v.into());
-        format!("$Env:{k}='{v}'\n")
+        set_env_script(k, v)
     }
 
     fn prepend_env(&self, k: &str, v: &str) -> String {
-        let k = powershell_escape(k.into());
-        let v = powershell_escape(v.into());
-        format!("$Env:{k}='{v}'+[IO.Path]::PathSeparator+$env:{k}\n")
+        prepend_env_script(k, v)
     }
 
     fn unset_env(&self, k: &str) -> String {
-        let k = powershell_escape(k.into());
-        format!("Remove-Item -Path Env:/{k}\n")
+        unset_env_script(k)
     }
 }
 
@@ -133,43 +54,6 @@ impl Display for Pwsh {
     }
 }
 
-fn powershell_escape(s: Cow<str>) -> Cow<str> {
-    let needs_escape = s.is_empty();
-
-    if !needs_escape {
-        return s;
-    }
-
-    let mut es = String::with_capacity(s.len());
-    let mut chars = s.chars().peekable();
-    loop {
-        match chars.next() {
-            Some('\t') => {
-                es.push_str("`t");
-            }
-            Some('\n') => {
-                es.push_str("`n");
-            }
-            Some('\r') => {
-                es.push_str("`r");
-            }
-            Some('\'') => {
-                es.push_str("`'");
-            }
-            Some('`') => {
-                es.push_str("``");
-            }
-            Some(c) => {
-                es.push(c);
-            }
-            None => {
-                break;
-            }
-        }
-    }
-    es.into()
-}
-
 #[cfg(test)]
 mod tests {
     use insta::assert_snapshot;
@@ -197,12 +81,38 @@ mod tests {
         assert_snapshot!(Pwsh::default().set_env("FOO", "1"));
     }
 
+    #[test]
+    fn test_set_env_with_space() {
+        assert_snapshot!(Pwsh::default().set_env("FOO", "hello world"));
+    }
+
+    #[test]
+    fn test_set_env_with_single_quote() {
+        assert_snapshot!(Pwsh::default().set_env("FOO", "it's a test"));
+    }
+
+    #[test]
+    fn test_set_env_with_dollar_sign() {
+        assert_snapshot!(Pwsh::default().set_env("FOO", "$HOME/bin"));
+    }
+
+    #[test]
+    fn test_set_env_with_newline() {
+        assert_snapshot!(Pwsh::default().set_env("FOO", "line one\nline two"));
+    }
+
     #[test]
     fn test_prepend_env() {
         let pwsh = Pwsh::default();
         assert_snapshot!(replace_path(&pwsh.prepend_env("PATH", "/some/dir:/2/dir")));
     }
 
+    #[test]
+    fn test_prepend_env_with_newline() {
+        let pwsh = Pwsh::default();
+        assert_snapshot!(pwsh.prepend_env("PATH", "line one\nline two"));
+    }
+
     #[test]
     fn test_unset_env() {
         assert_snapshot!(Pwsh::default().unset_env("FOO"));
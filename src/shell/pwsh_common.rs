@@ -0,0 +1,144 @@
+use indoc::formatdoc;
+
+// Shared building blocks for the PowerShell-family shells (pwsh.rs, powershell.rs). Both emit
+// the same `mise` wrapper function, `deactivate` script, and env-mutation lines; they differ
+// only in how they notice a directory change (pwsh 6.2+ has `LocationChangedAction`, Windows
+// PowerShell 5.1 only gets a wrapped `prompt`) and in the shell name they pass in, which is
+// reported to `hook-env -s`, names the binary the `mise` wrapper re-execs into, and prefixes
+// the stashed-prompt global.
+
+/// The `function mise { ... }` wrapper, identical across variants except for which binary it
+/// re-execs into to propagate `mise`'s exit code (`pwsh` vs `powershell`).
+pub(crate) fn mise_function(exe: &str, shell_exe: &str) -> String {
+    formatdoc! {r#"
+        function mise {{
+
+            $code = [System.Management.Automation.Language.Parser]::ParseInput($MyInvocation.Statement.Substring($MyInvocation.OffsetInLine - 1), [ref]$null, [ref]$null)
+            $myLine = $code.Find({{ $args[0].CommandElements }}, $true).CommandElements | ForEach-Object {{ $_.ToString() }} | Join-String -Separator ' '
+            $command, [array]$arguments = Invoke-Expression ('Write-Output -- ' + $myLine)
+
+            if ($null -eq $arguments) {{
+                & {exe}
+                return
+            }}
+
+            $command = $arguments[0]
+            $arguments = $arguments[1..$arguments.Length]
+
+            if ($arguments -contains '--help') {{
+                return & {exe} $command $arguments
+            }}
+
+            switch ($command) {{
+                {{ $_ -in 'deactivate', 'shell', 'sh' }} {{
+                    if ($arguments -contains '-h' -or $arguments -contains '--help') {{
+                        & {exe} $command $arguments
+                    }}
+                    else {{
+                        & {exe} $command $arguments | Out-String | Invoke-Expression -ErrorAction SilentlyContinue
+                    }}
+                }}
+                default {{
+                    & {exe} $command $arguments
+                    $status = $LASTEXITCODE
+                    if ($(Test-Path -Path Function:\_mise_hook)){{
+                        _mise_hook
+                    }}
+                    {shell_exe} -NoProfile -Command exit $status #Pass down exit code from mise after _mise_hook
+                }}
+            }}
+        }}
+        "#}
+}
+
+/// The `_mise_hook` function that re-runs `hook-env` for `shell_name`, shared by every variant.
+pub(crate) fn hook_function(exe: &str, flags: &str, shell_name: &str) -> String {
+    formatdoc! {r#"
+        function _mise_hook {{
+            if ($env:MISE_SHELL -eq "{shell_name}"){{
+                & {exe} hook-env{flags} -s {shell_name} | Out-String | Invoke-Expression -ErrorAction SilentlyContinue
+            }}
+        }}
+        "#}
+}
+
+/// Wraps the user's existing `prompt` function so `_mise_hook` runs before every prompt redraw.
+/// `var_prefix` namespaces the stashed-previous-prompt global so pwsh and PowerShell 5.1 (which
+/// may both be wired up in the same session via different profiles) don't clobber each other.
+pub(crate) fn prompt_wrap_hook(var_prefix: &str) -> String {
+    formatdoc! {r#"
+        if (-not $__mise_{var_prefix}_previous_prompt_function){{
+            $global:__mise_{var_prefix}_previous_prompt_function=$function:prompt
+            function global:prompt {{
+                if (Test-Path -Path Function:\_mise_hook){{
+                    _mise_hook
+                }}
+                & $__mise_{var_prefix}_previous_prompt_function
+            }}
+        }}
+        "#}
+}
+
+/// Hooks `LocationChangedAction`, which only exists on pwsh 6.2+. Windows PowerShell 5.1 must
+/// skip this and rely on [`prompt_wrap_hook`] alone.
+pub(crate) fn chpwd_hook() -> String {
+    formatdoc! {r#"
+        if (-not $__mise_pwsh_previous_chpwd_function){{
+            $_mise_chpwd_hook = [EventHandler[System.Management.Automation.LocationChangedEventArgs]] {{
+                param([object] $source, [System.Management.Automation.LocationChangedEventArgs] $eventArgs)
+                end {{
+                    _mise_hook
+                }}
+            }};
+            $Global:__mise_pwsh_previous_chpwd_function=$ExecutionContext.SessionState.InvokeCommand.LocationChangedAction;
+
+            if ($__mise_original_pwsh_chpwd_function) {{
+                $ExecutionContext.SessionState.InvokeCommand.LocationChangedAction = [Delegate]::Combine($__mise_pwsh_previous_chpwd_function, $_mise_chpwd_hook)
+            }}
+            else {{
+                $ExecutionContext.SessionState.InvokeCommand.LocationChangedAction = $_mise_chpwd_hook
+            }}
+        }}
+        "#}
+}
+
+pub(crate) fn deactivate_script() -> String {
+    formatdoc! {r#"
+    Remove-Item function:mise
+    Remove-Item -Path Env:/MISE_SHELL
+    Remove-Item -Path Env:/__MISE_WATCH
+    Remove-Item -Path Env:/__MISE_DIFF
+    "#}
+}
+
+/// Quote a value for use as a PowerShell single-quoted literal.
+///
+/// Inside `'...'` the *only* meaningful escape is doubling an embedded single quote (`'` ->
+/// `''`); backtick escapes don't apply there. Values containing a newline are emitted as a
+/// single-quoted here-string (`@'...'@`) instead, since a `'...'` literal can't span lines.
+pub(crate) fn powershell_quote(s: &str) -> String {
+    if s.contains('\n') {
+        format!("@'\n{s}\n'@")
+    } else {
+        let escaped = s.replace('\'', "''");
+        format!("'{escaped}'")
+    }
+}
+
+pub(crate) fn set_env_script(k: &str, v: &str) -> String {
+    let v = powershell_quote(v);
+    format!("$Env:{k}={v}\n")
+}
+
+/// The here-string form of [`powershell_quote`] requires its `'@` terminator to be the only
+/// thing on its line, so the value is assigned to a scratch variable first and the
+/// `PathSeparator`-joined concatenation happens on the following line rather than being
+/// appended directly after the quoted literal.
+pub(crate) fn prepend_env_script(k: &str, v: &str) -> String {
+    let v = powershell_quote(v);
+    format!("$__mise_prepend_value={v}\n$Env:{k}=$__mise_prepend_value+[IO.Path]::PathSeparator+$env:{k}\n")
+}
+
+pub(crate) fn unset_env_script(k: &str) -> String {
+    format!("Remove-Item -Path Env:/{k}\n")
+}